@@ -0,0 +1,257 @@
+//! AtAt metadata directive parsing.
+//!
+//! AtAt is the tiny `@key@ value @@` metadata format `cargo-task` uses to
+//! read configuration out of a task's `main.rs` (or single-file `.ct.rs`)
+//! source without requiring a real parser / extra dependency. See the
+//! crate-level docs for the full directive list.
+
+use std::collections::HashMap;
+
+/// The parsed AtAt directives pulled out of a single task source file.
+///
+/// Unknown directives are ignored, and any directive may be absent, in
+/// which case the field takes its default value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtAt {
+    /// `@ct-default@ true @@` - run this task when no tasks are named.
+    pub default: bool,
+
+    /// `@ct-bootstrap@ true @@` - always run this task first, and reload
+    /// task metadata afterwards.
+    pub bootstrap: bool,
+
+    /// `@ct-cargo-deps@ ... @@` - extra `[dependencies]` lines for a
+    /// single-file task. They are parsed only: no `Cargo.toml` is
+    /// generated from them.
+    pub cargo_deps: Vec<String>,
+
+    /// `@ct-task-deps@ ... @@` - names of other tasks that must run (and
+    /// complete) before this one.
+    pub task_deps: Vec<String>,
+
+    /// `@ct-min-version@ 0.0.7 @@` - minimum `cargo-task` version required
+    /// to run this task.
+    pub min_version: Option<String>,
+
+    /// `@ct-platform@ linux macos @@` - `std::env::consts::OS` values this
+    /// task is allowed to run on. Empty means "no restriction".
+    pub platform: Vec<String>,
+
+    /// `@ct-condition-env@ CI=true @@` - environment variables that must
+    /// be set (optionally to a specific value) for this task to run.
+    /// Each entry is either a bare `NAME` (present) or `NAME=value`
+    /// (equals).
+    pub condition_env: Vec<String>,
+
+    /// `@ct-install-crate@ cargo-nextest @@` - binary crates that must be
+    /// installed (via `cargo install`) before this task runs. Each entry
+    /// is a crate name, optionally with a version spec
+    /// (`cargo-nextest@0.9`) and/or an explicit binary name
+    /// (`ripgrep:rg`), for crates whose binary doesn't share the crate's
+    /// name. The binary name defaults to the crate name when omitted.
+    pub install_crate: Vec<String>,
+
+    /// Whether `@ct-install-crate@` included a bare `--force` token,
+    /// requesting a forced reinstall of every crate it names.
+    pub install_crate_force: bool,
+
+    /// `@ct-clean@` - extra paths (relative to the workspace root) this
+    /// task generates, for `ct-clean` to reclaim alongside its own build
+    /// artifacts.
+    pub clean_paths: Vec<String>,
+
+    /// `@ct-watch@` - extra paths (relative to the workspace root) that
+    /// should trigger a re-run under `cargo task --watch`, alongside this
+    /// task's own crate sources.
+    pub watch_paths: Vec<String>,
+}
+
+impl AtAt {
+    /// Scan `src` (the full contents of a task's `main.rs` / `.ct.rs` file)
+    /// and parse out every AtAt directive it recognizes.
+    pub fn parse(src: &str) -> Self {
+        let raw = parse_raw(src);
+
+        let default = raw
+            .get("ct-default")
+            .map(|v| v.trim() == "true")
+            .unwrap_or(false);
+
+        let bootstrap = raw
+            .get("ct-bootstrap")
+            .map(|v| v.trim() == "true")
+            .unwrap_or(false);
+
+        let cargo_deps = raw
+            .get("ct-cargo-deps")
+            .map(|v| split_lines(v))
+            .unwrap_or_default();
+
+        let task_deps = raw
+            .get("ct-task-deps")
+            .map(|v| split_whitespace(v))
+            .unwrap_or_default();
+
+        let min_version = raw.get("ct-min-version").map(|v| v.trim().to_string());
+
+        let platform = raw
+            .get("ct-platform")
+            .map(|v| split_whitespace(v))
+            .unwrap_or_default();
+
+        let condition_env = raw
+            .get("ct-condition-env")
+            .map(|v| split_whitespace(v))
+            .unwrap_or_default();
+
+        let mut install_crate = raw
+            .get("ct-install-crate")
+            .map(|v| split_whitespace(v))
+            .unwrap_or_default();
+        let install_crate_force = {
+            let before = install_crate.len();
+            install_crate.retain(|tok| tok != "--force");
+            before != install_crate.len()
+        };
+
+        let clean_paths = raw
+            .get("ct-clean")
+            .map(|v| split_lines(v))
+            .unwrap_or_default();
+
+        let watch_paths = raw
+            .get("ct-watch")
+            .map(|v| split_lines(v))
+            .unwrap_or_default();
+
+        Self {
+            default,
+            bootstrap,
+            cargo_deps,
+            task_deps,
+            min_version,
+            platform,
+            condition_env,
+            install_crate,
+            install_crate_force,
+            clean_paths,
+            watch_paths,
+        }
+    }
+}
+
+/// Find every `@key@ value @@` directive in `src` and return the raw,
+/// un-trimmed value text keyed by directive name (without the `@`s).
+///
+/// Only lines where the opening `@` is the very first character count as
+/// the start of a directive, per the AtAt protocol.
+fn parse_raw(src: &str) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+
+    let mut rest = src;
+    while let Some(line_start) = find_directive_line_start(rest) {
+        rest = &rest[line_start..];
+
+        let Some(key_end) = rest[1..].find('@') else {
+            break;
+        };
+        let key = &rest[1..1 + key_end];
+        let after_key = &rest[1 + key_end + 1..];
+
+        let Some(value_end) = after_key.find("@@") else {
+            break;
+        };
+        let value = &after_key[..value_end];
+
+        out.insert(key.to_string(), value.to_string());
+        rest = &after_key[value_end + 2..];
+    }
+
+    out
+}
+
+/// Find the byte offset, within `src`, of the next `@` that starts a line
+/// (i.e. is either the first character of `src` or immediately follows a
+/// newline).
+fn find_directive_line_start(src: &str) -> Option<usize> {
+    if src.starts_with('@') {
+        return Some(0);
+    }
+    let mut search_from = 0;
+    while let Some(idx) = src[search_from..].find('@') {
+        let at = search_from + idx;
+        if src.as_bytes()[at - 1] == b'\n' {
+            return Some(at);
+        }
+        search_from = at + 1;
+    }
+    None
+}
+
+/// Split a directive value into non-empty, trimmed lines.
+fn split_lines(value: &str) -> Vec<String> {
+    value
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Split a directive value on any whitespace (including newlines) into
+/// non-empty tokens.
+fn split_whitespace(value: &str) -> Vec<String> {
+    value
+        .split_whitespace()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_basic_directive() {
+        let src = r#"
+/*
+@ct-default@ true @@
+@ct-bootstrap@ true @@
+@ct-cargo-deps@
+num_cpus = "1"
+serde = { version = "1" }
+@@
+@ct-task-deps@
+one
+two
+@@
+@ct-min-version@ 0.0.7 @@
+*/
+"#;
+        let at_at = AtAt::parse(src);
+
+        assert!(at_at.default);
+        assert!(at_at.bootstrap);
+        assert_eq!(
+            at_at.cargo_deps,
+            vec!["num_cpus = \"1\"", "serde = { version = \"1\" }"]
+        );
+        assert_eq!(at_at.task_deps, vec!["one", "two"]);
+        assert_eq!(at_at.min_version.as_deref(), Some("0.0.7"));
+    }
+
+    #[test]
+    fn missing_directives_take_their_defaults() {
+        let at_at = AtAt::parse("fn main() {}");
+        assert_eq!(at_at, AtAt::default());
+    }
+
+    #[test]
+    fn only_a_directive_at_line_start_counts() {
+        // The `@` in the doc comment below isn't the first character of
+        // its line, so it must not be mistaken for a directive.
+        let src = "// see foo@bar for details\n@ct-default@ true @@";
+        let at_at = AtAt::parse(src);
+        assert!(at_at.default);
+    }
+}
@@ -0,0 +1,166 @@
+//! Helpers made available to every `cargo-task` task.
+//!
+//! This file is embedded in the `cargo-task` binary itself and copied
+//! verbatim into your `.cargo-task` crates as `cargo_task_util.rs` every
+//! time they're built - don't bother editing your copy, it'll just be
+//! overwritten. Add `mod cargo_task_util;` to your task's `main.rs` to
+//! pull it in.
+
+use std::path::PathBuf;
+
+/// The env var `cargo-task` sets to the invoking crate's manifest
+/// directory.
+const ENV_MANIFEST_DIR: &str = "CARGO_TASK_MANIFEST_DIR";
+/// The env var `cargo-task` sets to the `.cargo-task` directory.
+const ENV_CARGO_TASK_DIR: &str = "CARGO_TASK_DIR";
+/// The env var `cargo-task` sets to the directory task build artifacts
+/// are placed in.
+const ENV_TARGET_DIR: &str = "CARGO_TASK_TARGET_DIR";
+/// The env var `cargo-task` sets to the name of the task currently
+/// executing.
+const ENV_TASK_NAME: &str = "CARGO_TASK_NAME";
+/// The env var `cargo-task` sets to the path of the file `set_env` calls
+/// are appended to.
+const ENV_EXPORT_FILE: &str = "CARGO_TASK_ENV_EXPORT_FILE";
+/// The env var `cargo-task` sets to the path of the `cargo metadata`
+/// snapshot `workspace` reads from, if one was taken.
+const ENV_WORKSPACE_FILE: &str = "CARGO_TASK_WORKSPACE_FILE";
+/// The env var `cargo-task` sets to the resolved `cargo` binary path.
+const ENV_CARGO_BIN: &str = "CARGO_TASK_CARGO_BIN";
+/// The env var `cargo-task` sets to the resolved `CARGO_HOME`, if one was
+/// set.
+const ENV_CARGO_HOME: &str = "CARGO_TASK_CARGO_HOME";
+
+/// Metadata and helpers describing the currently running task, handed to
+/// you by [`ct_env`].
+#[derive(Debug, Clone)]
+pub struct CTEnv {
+    /// The manifest directory of the crate `cargo task` was invoked
+    /// against.
+    pub manifest_dir: PathBuf,
+
+    /// The `.cargo-task` directory task sources are loaded from.
+    pub cargo_task_dir: PathBuf,
+
+    /// The directory task crate build artifacts are written to.
+    pub target_dir: PathBuf,
+
+    /// The name of the task currently executing.
+    pub task_name: String,
+
+    /// The `cargo` binary `cargo-task` used to build and run this task -
+    /// the same one Cargo itself was invoked with (via `$CARGO`), not
+    /// just whatever `cargo` happens to resolve to on `PATH`. Launch
+    /// further cargo commands with this to stay on the same toolchain.
+    pub cargo_bin: PathBuf,
+
+    /// The resolved `CARGO_HOME`, if one was set for this invocation.
+    pub cargo_home: Option<PathBuf>,
+
+    /// Where [`CTEnv::set_env`] writes its `KEY=VALUE` lines.
+    env_export_file: PathBuf,
+
+    /// Where the `cargo metadata` snapshot [`CTEnv::workspace`] reads
+    /// lives, if `cargo-task` was able to take one.
+    workspace_file: Option<PathBuf>,
+}
+
+/// One workspace member package, as surfaced by `cargo metadata`.
+#[derive(Debug, Clone)]
+pub struct PackageMetadata {
+    /// The package's `name`.
+    pub name: String,
+    /// The package's `version`.
+    pub version: String,
+    /// Absolute path to the package's `Cargo.toml`.
+    pub manifest_path: PathBuf,
+}
+
+/// The subset of `cargo metadata` that [`CTEnv::workspace`] surfaces.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// The workspace's shared `target` directory.
+    pub target_directory: PathBuf,
+    /// Every workspace member package.
+    pub members: Vec<PackageMetadata>,
+}
+
+impl CTEnv {
+    /// Export an environment variable that will be visible to every task
+    /// that runs after this one, for the remainder of this `cargo task`
+    /// invocation.
+    ///
+    /// A plain `std::env::set_var` only affects the current process, so
+    /// use this instead - it's most useful from a `@ct-bootstrap@` task.
+    pub fn set_env(&self, name: &str, value: &str) {
+        use std::io::Write;
+
+        let Ok(mut file) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.env_export_file)
+        else {
+            return;
+        };
+
+        let _ = writeln!(file, "{name}={value}");
+    }
+
+    /// The invoking crate's workspace metadata - member package names,
+    /// versions, manifest paths, and the shared target directory -
+    /// as captured by `cargo-task`'s one `cargo metadata` call for this
+    /// invocation.
+    ///
+    /// Returns `None` if `cargo-task` wasn't able to run `cargo metadata`
+    /// (for example, if it's being run outside of a cargo workspace).
+    pub fn workspace(&self) -> Option<Workspace> {
+        let path = self.workspace_file.as_ref()?;
+        let contents = std::fs::read_to_string(path).ok()?;
+
+        let mut target_directory = None;
+        let mut members = Vec::new();
+
+        for line in contents.lines() {
+            let mut fields = line.split('\t');
+            match fields.next()? {
+                "target_directory" => target_directory = Some(PathBuf::from(fields.next()?)),
+                "member" => members.push(PackageMetadata {
+                    name: fields.next()?.to_string(),
+                    version: fields.next()?.to_string(),
+                    manifest_path: PathBuf::from(fields.next()?),
+                }),
+                _ => continue,
+            }
+        }
+
+        Some(Workspace {
+            target_directory: target_directory?,
+            members,
+        })
+    }
+}
+
+/// Read the [`CTEnv`] for the currently executing task out of the
+/// environment variables `cargo-task` set before launching it.
+pub fn ct_env() -> CTEnv {
+    CTEnv {
+        manifest_dir: std::env::var(ENV_MANIFEST_DIR).unwrap_or_default().into(),
+        cargo_task_dir: std::env::var(ENV_CARGO_TASK_DIR).unwrap_or_default().into(),
+        target_dir: std::env::var(ENV_TARGET_DIR).unwrap_or_default().into(),
+        task_name: std::env::var(ENV_TASK_NAME).unwrap_or_default(),
+        cargo_bin: std::env::var(ENV_CARGO_BIN)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("cargo")),
+        cargo_home: std::env::var(ENV_CARGO_HOME).ok().map(PathBuf::from),
+        env_export_file: std::env::var(ENV_EXPORT_FILE).unwrap_or_default().into(),
+        workspace_file: std::env::var(ENV_WORKSPACE_FILE).ok().map(PathBuf::from),
+    }
+}
+
+/// Print a `cargo-task`-flavored warning to stderr.
+#[macro_export]
+macro_rules! ct_warn {
+    ($($arg:tt)*) => {
+        eprintln!("[cargo-task:warn] {}", format!($($arg)*));
+    };
+}
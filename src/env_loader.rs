@@ -0,0 +1,287 @@
+//! Discovers the set of tasks defined in a project's `.cargo-task`
+//! directory.
+
+use crate::json::Value;
+use crate::task::{Task, TaskKind};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Walk `cargo_task_dir` (normally `./.cargo-task`) and load every task
+/// found there, in directory-listing order.
+///
+/// A task is either:
+/// - a sub-directory containing a `Cargo.toml` and a `src/main.rs`, or
+/// - a single `<name>.ct.rs` file directly inside `cargo_task_dir`.
+pub fn load_tasks(cargo_task_dir: &Path) -> io::Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+
+    if !cargo_task_dir.is_dir() {
+        return Ok(tasks);
+    }
+
+    for entry in fs::read_dir(cargo_task_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            let main_rs = path.join("src").join("main.rs");
+            if !main_rs.is_file() {
+                continue;
+            }
+            let name = dir_task_name(&path);
+            let src = fs::read_to_string(&main_rs)?;
+            tasks.push(Task::new(name, TaskKind::Crate { main_rs }, &src));
+        } else if file_type.is_file() {
+            let Some(name) = single_file_task_name(&path) else {
+                continue;
+            };
+            let src = fs::read_to_string(&path)?;
+            tasks.push(Task::new(name, TaskKind::SingleFile { path }, &src));
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Derive a task name from a task crate directory, i.e. its final path
+/// component.
+fn dir_task_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Derive a task name from a single-file task path, stripping the
+/// required `.ct.rs` suffix. Returns `None` for files that aren't
+/// single-file tasks.
+fn single_file_task_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_string_lossy();
+    file_name.strip_suffix(".ct.rs").map(|n| n.to_string())
+}
+
+/// The file `cargo_task_util::CTEnv::set_env` appends `KEY=VALUE` lines
+/// to, so that variables exported by one task become visible to every
+/// task that runs after it within this `cargo task` invocation.
+pub fn env_export_file(cargo_task_dir: &Path) -> PathBuf {
+    cargo_task_dir.join(".env-export")
+}
+
+/// Read back anything written to `env_export_file` and apply it to the
+/// current process's environment, so it's inherited by every `cargo`
+/// child process spawned for the rest of this invocation.
+pub fn apply_exported_env(env_export_file: &Path) -> io::Result<()> {
+    let Ok(contents) = fs::read_to_string(env_export_file) else {
+        return Ok(());
+    };
+
+    for line in contents.lines() {
+        if let Some((name, value)) = line.split_once('=') {
+            std::env::set_var(name, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// The file `ct-clean` reads to learn which per-task subdirectories of
+/// the managed target directory `cargo-task` has itself created, so it
+/// only ever reclaims build output it's responsible for. This matters
+/// because `CARGO_TARGET_DIR` (see `resolve_target_dir` in `exec`) may
+/// point at a directory shared with the workspace's own `debug`/
+/// `release`/`doc` output - `ct-clean` must never guess at what else
+/// lives there.
+pub fn managed_dirs_file(cargo_task_dir: &Path) -> PathBuf {
+    cargo_task_dir.join(".managed-dirs")
+}
+
+/// Record that `cargo-task` has created (or is about to create) a
+/// per-task build directory named `task_name`, so `ct-clean` knows it's
+/// safe to reclaim later. Recording the same name twice is harmless.
+pub fn record_managed_dir(cargo_task_dir: &Path, task_name: &str) -> io::Result<()> {
+    if read_managed_dirs(cargo_task_dir)?.contains(task_name) {
+        return Ok(());
+    }
+
+    use std::io::Write;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(managed_dirs_file(cargo_task_dir))?;
+    writeln!(file, "{task_name}")
+}
+
+/// Read back every directory name [`record_managed_dir`] has ever
+/// recorded for this `.cargo-task` directory.
+pub fn read_managed_dirs(cargo_task_dir: &Path) -> io::Result<HashSet<String>> {
+    match fs::read_to_string(managed_dirs_file(cargo_task_dir)) {
+        Ok(contents) => Ok(contents.lines().map(str::to_string).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashSet::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// One workspace member package, as surfaced by `cargo metadata`.
+#[derive(Debug, Clone)]
+pub struct PackageMetadata {
+    /// The package's `name`.
+    pub name: String,
+    /// The package's `version`.
+    pub version: String,
+    /// Absolute path to the package's `Cargo.toml`.
+    pub manifest_path: PathBuf,
+}
+
+/// The subset of `cargo metadata --format-version=1` that `cargo-task`
+/// surfaces to tasks via `cargo_task_util::CTEnv::workspace`.
+#[derive(Debug, Clone)]
+pub struct WorkspaceMetadata {
+    /// The workspace's shared `target` directory.
+    pub target_directory: PathBuf,
+    /// Every workspace member package.
+    pub members: Vec<PackageMetadata>,
+}
+
+/// The cargo binary to shell out to for every sub-invocation: the path
+/// Cargo itself sets in `$CARGO` when it runs `cargo-task` as a
+/// subcommand, falling back to plain `cargo` on `PATH` when it's unset
+/// (e.g. `cargo-task` invoked directly rather than as `cargo task`).
+pub fn cargo_bin() -> PathBuf {
+    std::env::var_os("CARGO")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("cargo"))
+}
+
+/// Shell out to `cargo metadata --format-version=1 --no-deps` against
+/// `manifest_dir` and hand-parse out just the fields `cargo-task` needs.
+pub fn load_workspace_metadata(manifest_dir: &Path) -> io::Result<WorkspaceMetadata> {
+    let output = Command::new(cargo_bin())
+        .arg("metadata")
+        .arg("--format-version=1")
+        .arg("--no-deps")
+        .current_dir(manifest_dir)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let root = crate::json::parse(&text).map_err(io::Error::other)?;
+    parse_workspace_metadata(&root).map_err(io::Error::other)
+}
+
+/// Pull `target_directory` and each member's `name` / `version` /
+/// `manifest_path` out of a parsed `cargo metadata` document.
+fn parse_workspace_metadata(root: &Value) -> Result<WorkspaceMetadata, String> {
+    let target_directory = root
+        .get("target_directory")
+        .and_then(Value::as_str)
+        .ok_or("missing 'target_directory'")?
+        .into();
+
+    let packages = root
+        .get("packages")
+        .and_then(Value::as_array)
+        .ok_or("missing 'packages'")?;
+
+    let members = packages
+        .iter()
+        .map(|pkg| {
+            Ok(PackageMetadata {
+                name: pkg
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("package missing 'name'")?
+                    .to_string(),
+                version: pkg
+                    .get("version")
+                    .and_then(Value::as_str)
+                    .ok_or("package missing 'version'")?
+                    .to_string(),
+                manifest_path: pkg
+                    .get("manifest_path")
+                    .and_then(Value::as_str)
+                    .ok_or("package missing 'manifest_path'")?
+                    .into(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(WorkspaceMetadata {
+        target_directory,
+        members,
+    })
+}
+
+/// Where [`write_workspace_snapshot`] writes its serialized snapshot of a
+/// [`WorkspaceMetadata`], for a running task to read back via
+/// `cargo_task_util::CTEnv::workspace`.
+pub fn workspace_snapshot_file(cargo_task_dir: &Path) -> PathBuf {
+    cargo_task_dir.join(".workspace-snapshot")
+}
+
+/// Serialize `workspace` to `path` in the tab-separated format
+/// `cargo_task_util::CTEnv::workspace` knows how to read back, so a
+/// running task doesn't need a JSON parser of its own.
+pub fn write_workspace_snapshot(workspace: &WorkspaceMetadata, path: &Path) -> io::Result<()> {
+    let mut out = format!("target_directory\t{}\n", workspace.target_directory.display());
+    for member in &workspace.members {
+        out.push_str(&format!(
+            "member\t{}\t{}\t{}\n",
+            member.name,
+            member.version,
+            member.manifest_path.display()
+        ));
+    }
+    fs::write(path, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh, empty directory under the system temp dir, cleaned up
+    /// when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "cargo-task-test-{label}-{:?}-{}",
+                std::thread::current().id(),
+                std::process::id()
+            ));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn managed_dirs_round_trip_and_dedupe() {
+        let dir = TempDir::new("managed-dirs");
+
+        assert!(read_managed_dirs(&dir.0).unwrap().is_empty());
+
+        record_managed_dir(&dir.0, "one").unwrap();
+        record_managed_dir(&dir.0, "two").unwrap();
+        record_managed_dir(&dir.0, "one").unwrap();
+
+        let managed = read_managed_dirs(&dir.0).unwrap();
+        assert_eq!(managed.len(), 2);
+        assert!(managed.contains("one"));
+        assert!(managed.contains("two"));
+    }
+}
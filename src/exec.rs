@@ -0,0 +1,1022 @@
+//! Task execution: discovering, ordering, and running `.cargo-task`
+//! tasks, including dependency resolution between them.
+
+use crate::at_at::AtAt;
+use crate::env_loader;
+use crate::task::{Task, TaskKind};
+use crate::{CARGO_TASK_DIR, CARGO_TASK_UTIL_SRC, CT_DIR_GIT_IGNORE, CT_DIR_GIT_IGNORE_SRC};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// The env var a running task's `cargo_task_util::ct_env()` reads its
+/// manifest directory from.
+const ENV_MANIFEST_DIR: &str = "CARGO_TASK_MANIFEST_DIR";
+/// The env var a running task's `cargo_task_util::ct_env()` reads the
+/// `.cargo-task` directory from.
+const ENV_CARGO_TASK_DIR: &str = "CARGO_TASK_DIR";
+/// The env var a running task's `cargo_task_util::ct_env()` reads its
+/// target directory from.
+const ENV_TARGET_DIR: &str = "CARGO_TASK_TARGET_DIR";
+/// The env var a running task's `cargo_task_util::ct_env()` reads its own
+/// task name from.
+const ENV_TASK_NAME: &str = "CARGO_TASK_NAME";
+/// The env var a running task's `cargo_task_util::CTEnv::set_env` writes
+/// its exports to.
+const ENV_EXPORT_FILE: &str = "CARGO_TASK_ENV_EXPORT_FILE";
+/// The env var a running task's `cargo_task_util::CTEnv::workspace`
+/// reads its `cargo metadata` snapshot from.
+const ENV_WORKSPACE_FILE: &str = "CARGO_TASK_WORKSPACE_FILE";
+/// The env var a running task's `cargo_task_util::CTEnv` reads the
+/// resolved `cargo` binary path from.
+const ENV_CARGO_BIN: &str = "CARGO_TASK_CARGO_BIN";
+/// The env var a running task's `cargo_task_util::CTEnv` reads the
+/// resolved `CARGO_HOME` from, if one was set.
+const ENV_CARGO_HOME: &str = "CARGO_TASK_CARGO_HOME";
+
+/// Resolve the base directory task crates build into: `CARGO_TARGET_DIR`
+/// if the user set one, otherwise a directory under `.cargo-task`. Each
+/// task gets its own subdirectory of this, named after the task.
+fn resolve_target_dir(cargo_task_dir: &std::path::Path) -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| cargo_task_dir.join("target"))
+}
+
+/// Options controlling a single `cargo task` invocation.
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    /// `--jobs N` - run up to `N` independent tasks concurrently. `None`
+    /// or `Some(1)` keeps the original one-at-a-time behavior.
+    pub jobs: Option<usize>,
+
+    /// `--all`, for `cargo task ct-clean --all` - reclaim every managed
+    /// build artifact, not just ones orphaned by a task that no longer
+    /// exists.
+    pub clean_all: bool,
+
+    /// `--watch <path...>`, for a standing dev loop - once set, `run`
+    /// never returns on its own: it runs the requested tasks, then
+    /// re-runs them every time a watched path changes, until
+    /// interrupted.
+    pub watch: Option<WatchOptions>,
+}
+
+/// Options for `cargo task --watch <path...>`.
+#[derive(Debug, Clone, Default)]
+pub struct WatchOptions {
+    /// Extra paths (files or directories, relative to the workspace
+    /// root) to watch, given on the command line, in addition to each
+    /// watched task's own crate sources and its `@ct-watch@` directive.
+    pub paths: Vec<String>,
+}
+
+/// How long to wait after detecting a change for further changes to stop
+/// arriving before re-running - collapses a burst of saves (an editor
+/// writing several files at once, a codegen task touching many outputs)
+/// into a single re-run.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often to poll watched paths for mtime changes.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Names of tasks `cargo-task` handles itself, rather than looking for
+/// in `.cargo-task`.
+const BUILTIN_TASKS: &[&str] = &["ct-init", "ct-meta", "ct-clean"];
+
+/// Run the named tasks (and their transitive `@ct-task-deps@`) to
+/// completion.
+///
+/// Bootstrap tasks (`@ct-bootstrap@ true @@`) always run first, as a
+/// barrier, and task metadata is reloaded afterwards so bootstrap tasks
+/// can create or modify other tasks before the requested task list runs.
+///
+/// If `opts.watch` is set, this never returns on its own: see
+/// [`run_watch`].
+pub fn run(task_names: &[String], opts: &ExecOptions) -> io::Result<()> {
+    if let Some(watch) = opts.watch.clone() {
+        return run_watch(task_names, opts, &watch);
+    }
+    run_once(task_names, opts)
+}
+
+/// Run the requested tasks once, wiring up bootstrap and dependency
+/// resolution. See [`run`].
+fn run_once(task_names: &[String], opts: &ExecOptions) -> io::Result<()> {
+    let cargo_task_dir = PathBuf::from(CARGO_TASK_DIR);
+
+    let (builtins, task_names): (Vec<&String>, Vec<&String>) = task_names
+        .iter()
+        .partition(|name| BUILTIN_TASKS.contains(&name.as_str()));
+    for name in builtins {
+        run_builtin(name, &cargo_task_dir, opts)?;
+    }
+
+    let mut tasks = env_loader::load_tasks(&cargo_task_dir)?;
+    run_bootstrap_barrier(&tasks, &cargo_task_dir)?;
+    env_loader::apply_exported_env(&env_loader::env_export_file(&cargo_task_dir))?;
+    tasks = env_loader::load_tasks(&cargo_task_dir)?;
+
+    let task_names: Vec<String> = task_names.into_iter().cloned().collect();
+    let task_names = default_task_names_if_empty(&tasks, &task_names);
+    if task_names.is_empty() {
+        return Ok(());
+    }
+    let graph = TaskGraph::build(&tasks, &task_names, &cargo_task_dir)?;
+
+    let env = load_env_snapshot();
+    let jobs = opts.jobs.unwrap_or(1).max(1);
+    if jobs > 1 {
+        graph.run_parallel(jobs, &env)
+    } else {
+        graph.run_sequential(&env)
+    }
+}
+
+/// Handle one of the [`BUILTIN_TASKS`].
+fn run_builtin(name: &str, cargo_task_dir: &std::path::Path, opts: &ExecOptions) -> io::Result<()> {
+    match name {
+        "ct-init" => ct_init(cargo_task_dir),
+        "ct-meta" => ct_meta(cargo_task_dir),
+        "ct-clean" => ct_clean(cargo_task_dir, opts.clean_all),
+        _ => Err(io::Error::other(format!("unknown builtin task '{name}'"))),
+    }
+}
+
+/// `cargo task ct-init` - create the `.cargo-task` directory (if it
+/// doesn't already exist) along with its `.gitignore`.
+fn ct_init(cargo_task_dir: &std::path::Path) -> io::Result<()> {
+    std::fs::create_dir_all(cargo_task_dir)?;
+    std::fs::write(CT_DIR_GIT_IGNORE, CT_DIR_GIT_IGNORE_SRC)?;
+    Ok(())
+}
+
+/// `cargo task ct-meta` - print out the discovered task metadata.
+fn ct_meta(cargo_task_dir: &std::path::Path) -> io::Result<()> {
+    let tasks = env_loader::load_tasks(cargo_task_dir)?;
+    println!("{tasks:#?}");
+    Ok(())
+}
+
+/// `cargo task ct-clean` - reclaim build artifacts under the managed
+/// target directory.
+///
+/// Only ever touches per-task subdirectories `run_one` has itself
+/// recorded via [`env_loader::record_managed_dir`] - never anything else
+/// living under the target base, and never the target base directory
+/// itself. That matters because `CARGO_TARGET_DIR` may point at a
+/// directory shared with the workspace's own `debug`/`release`/`doc`
+/// output, which `ct-clean` must never guess at or wipe.
+///
+/// With `all` set (`cargo task ct-clean --all`), every managed task
+/// directory is reclaimed, live or not. Otherwise, only the ones that
+/// don't belong to any currently discovered task are removed - build
+/// output left behind by a task that's since been renamed or deleted.
+/// Either way, every current task's `@ct-clean@` paths are also
+/// reclaimed.
+fn ct_clean(cargo_task_dir: &std::path::Path, all: bool) -> io::Result<()> {
+    let tasks = env_loader::load_tasks(cargo_task_dir)?;
+    let target_base = resolve_target_dir(cargo_task_dir);
+    let managed = env_loader::read_managed_dirs(cargo_task_dir)?;
+    let live: HashSet<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+    let mut reclaimed = 0u64;
+
+    for name in &managed {
+        if !all && live.contains(name.as_str()) {
+            continue;
+        }
+        let path = target_base.join(name);
+        if path.is_dir() {
+            reclaimed += dir_size(&path);
+            std::fs::remove_dir_all(&path)?;
+        }
+    }
+
+    for task in &tasks {
+        for clean_path in &task.at_at.clean_paths {
+            let path = PathBuf::from(clean_path);
+            if path.is_dir() {
+                reclaimed += dir_size(&path);
+                std::fs::remove_dir_all(&path)?;
+            } else if path.is_file() {
+                reclaimed += std::fs::metadata(&path)?.len();
+                std::fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    println!("ct-clean: reclaimed {reclaimed} bytes");
+    Ok(())
+}
+
+/// Total size, in bytes, of every file under `path` (recursively).
+fn dir_size(path: &std::path::Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            total += dir_size(&entry.path());
+        } else if let Ok(metadata) = entry.metadata() {
+            total += metadata.len();
+        }
+    }
+    total
+}
+
+/// If the caller passed no task names, fall back to every task marked
+/// `@ct-default@ true @@`.
+fn default_task_names_if_empty(tasks: &[Task], task_names: &[String]) -> Vec<String> {
+    if !task_names.is_empty() {
+        return task_names.to_vec();
+    }
+    tasks
+        .iter()
+        .filter(|t| t.at_at.default)
+        .map(|t| t.name.clone())
+        .collect()
+}
+
+/// Run every bootstrap task, in file-discovery order, as a single
+/// sequential barrier before anything else happens.
+fn run_bootstrap_barrier(tasks: &[Task], cargo_task_dir: &std::path::Path) -> io::Result<()> {
+    for task in tasks.iter().filter(|t| t.at_at.bootstrap) {
+        run_one(task, cargo_task_dir, &load_env_snapshot())?;
+        env_loader::apply_exported_env(&env_loader::env_export_file(cargo_task_dir))?;
+    }
+    Ok(())
+}
+
+/// Snapshot the current process environment once, so `skip_reason` reads
+/// through an explicitly loaded set of variables - mirroring how a
+/// running task's own `cargo_task_util::ct_env()` reads a snapshot rather
+/// than querying `std::env` ad hoc - instead of each condition check
+/// hitting ambient global state directly.
+fn load_env_snapshot() -> HashMap<String, String> {
+    std::env::vars().collect()
+}
+
+/// Why a task was skipped rather than run, if it was.
+fn skip_reason(at_at: &AtAt, env: &HashMap<String, String>) -> Option<String> {
+    if !at_at.platform.is_empty() {
+        let os = std::env::consts::OS;
+        if !at_at.platform.iter().any(|p| p == os) {
+            return Some(format!(
+                "platform '{os}' not in [{}]",
+                at_at.platform.join(", ")
+            ));
+        }
+    }
+
+    for condition in &at_at.condition_env {
+        let met = match condition.split_once('=') {
+            Some((name, value)) => env.get(name).map(|v| v == value).unwrap_or(false),
+            None => env.contains_key(condition),
+        };
+        if !met {
+            return Some(format!("condition-env '{condition}' not met"));
+        }
+    }
+
+    None
+}
+
+/// A queue of task names that have become ready to run, shared by every
+/// worker in [`TaskGraph::run_parallel`].
+///
+/// Built on a `Mutex` + `Condvar` rather than a shared `Mutex<Receiver>`:
+/// `Condvar::wait` releases the mutex for the duration of the wait, so
+/// any number of idle workers can block on new work arriving at once,
+/// instead of serializing on the mutex itself.
+struct ReadyQueue {
+    state: Mutex<ReadyState>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct ReadyState {
+    queue: VecDeque<String>,
+    closed: bool,
+}
+
+impl ReadyQueue {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(ReadyState::default()),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Make `name` available to the next worker that calls [`Self::pop`].
+    fn push(&self, name: String) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(name);
+        self.cond.notify_one();
+    }
+
+    /// Wake every worker still waiting in [`Self::pop`] so they can
+    /// notice there's no more work coming and return.
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        self.cond.notify_all();
+    }
+
+    /// Block until a task name is ready to run, or the queue is closed
+    /// with nothing left in it.
+    fn pop(&self) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(name) = state.queue.pop_front() {
+                return Some(name);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.cond.wait(state).unwrap();
+        }
+    }
+}
+
+/// A directed acyclic graph of tasks to run, built from the requested
+/// task names plus their transitive `@ct-task-deps@`.
+#[derive(Debug)]
+struct TaskGraph {
+    /// Every task that must run, keyed by name. A task named twice (as a
+    /// direct request and as someone else's dependency, say) only ever
+    /// gets a single node here.
+    nodes: HashMap<String, Task>,
+    /// dependency name -> names of tasks that depend on it.
+    dependents: HashMap<String, Vec<String>>,
+    /// task name -> number of not-yet-completed dependencies.
+    in_degree: HashMap<String, usize>,
+    /// The `.cargo-task` directory tasks in this graph were loaded from.
+    cargo_task_dir: PathBuf,
+}
+
+impl TaskGraph {
+    /// Collect `wanted` and everything it transitively depends on into a
+    /// graph, failing if a dependency cycle or unknown task name is
+    /// found.
+    fn build(all_tasks: &[Task], wanted: &[String], cargo_task_dir: &std::path::Path) -> io::Result<Self> {
+        let by_name: HashMap<&str, &Task> =
+            all_tasks.iter().map(|t| (t.name.as_str(), t)).collect();
+
+        let mut nodes = HashMap::new();
+        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        let mut in_degree: HashMap<String, usize> = HashMap::new();
+
+        let mut queue: VecDeque<String> = wanted.to_vec().into();
+        let mut seen: HashSet<String> = queue.iter().cloned().collect();
+
+        while let Some(name) = queue.pop_front() {
+            let task = *by_name
+                .get(name.as_str())
+                .ok_or_else(|| io::Error::other(format!("unknown task '{name}'")))?;
+
+            in_degree.entry(name.clone()).or_insert(0);
+
+            for dep in &task.at_at.task_deps {
+                dependents
+                    .entry(dep.clone())
+                    .or_default()
+                    .push(name.clone());
+                *in_degree.entry(name.clone()).or_insert(0) += 1;
+
+                if seen.insert(dep.clone()) {
+                    queue.push_back(dep.clone());
+                }
+            }
+
+            nodes.insert(name, task.clone());
+        }
+
+        let graph = Self {
+            nodes,
+            dependents,
+            in_degree,
+            cargo_task_dir: cargo_task_dir.to_path_buf(),
+        };
+        graph.check_acyclic()?;
+        Ok(graph)
+    }
+
+    /// Kahn's algorithm, run without consuming state, purely to detect a
+    /// cycle up front and report it clearly.
+    fn check_acyclic(&self) -> io::Result<()> {
+        let mut in_degree = self.in_degree.clone();
+        let mut ready: VecDeque<String> = in_degree
+            .iter()
+            .filter(|(_, &d)| d == 0)
+            .map(|(n, _)| n.clone())
+            .collect();
+        let mut visited = 0;
+
+        while let Some(name) = ready.pop_front() {
+            visited += 1;
+            if let Some(deps) = self.dependents.get(&name) {
+                for dependent in deps {
+                    let d = in_degree.get_mut(dependent).unwrap();
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push_back(dependent.clone());
+                    }
+                }
+            }
+        }
+
+        if visited != self.nodes.len() {
+            let cycle: Vec<&str> = in_degree
+                .iter()
+                .filter(|(_, &d)| d > 0)
+                .map(|(n, _)| n.as_str())
+                .collect();
+            return Err(io::Error::other(format!(
+                "task dependency cycle detected among: {}",
+                cycle.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Run every task in the graph one at a time, in dependency order.
+    fn run_sequential(&self, env: &HashMap<String, String>) -> io::Result<()> {
+        let mut in_degree = self.in_degree.clone();
+        let mut ready: VecDeque<String> = in_degree
+            .iter()
+            .filter(|(_, &d)| d == 0)
+            .map(|(n, _)| n.clone())
+            .collect();
+
+        let mut remaining = self.nodes.len();
+        while let Some(name) = ready.pop_front() {
+            run_one(&self.nodes[&name], &self.cargo_task_dir, env)?;
+            remaining -= 1;
+
+            if let Some(deps) = self.dependents.get(&name) {
+                for dependent in deps {
+                    let d = in_degree.get_mut(dependent).unwrap();
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push_back(dependent.clone());
+                    }
+                }
+            }
+        }
+
+        debug_assert_eq!(remaining, 0);
+        Ok(())
+    }
+
+    /// Run the graph with up to `jobs` tasks executing concurrently on
+    /// `std::thread` workers, respecting dependency order.
+    fn run_parallel(&self, jobs: usize, env: &HashMap<String, String>) -> io::Result<()> {
+        let in_degree = Mutex::new(self.in_degree.clone());
+        let (done_tx, done_rx) = mpsc::channel::<(String, io::Result<()>)>();
+        let ready = ReadyQueue::new();
+
+        let initial_ready: Vec<String> = in_degree
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, &d)| d == 0)
+            .map(|(n, _)| n.clone())
+            .collect();
+        for name in initial_ready {
+            ready.push(name);
+        }
+
+        let total = self.nodes.len();
+        let mut completed = 0;
+        let mut first_err: Option<io::Error> = None;
+
+        std::thread::scope(|scope| {
+            let workers = jobs.min(total.max(1));
+            for _ in 0..workers {
+                let ready = &ready;
+                let done_tx = done_tx.clone();
+                let nodes = &self.nodes;
+                let cargo_task_dir = &self.cargo_task_dir;
+                scope.spawn(move || {
+                    while let Some(name) = ready.pop() {
+                        let result = run_one(&nodes[&name], cargo_task_dir, env);
+                        if done_tx.send((name, result)).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(done_tx);
+
+            while completed < total {
+                let Ok((name, result)) = done_rx.recv() else {
+                    break;
+                };
+                completed += 1;
+                if let Err(e) = result {
+                    first_err = Some(e);
+                    break;
+                }
+
+                if let Some(dependents) = self.dependents.get(&name) {
+                    let mut in_degree = in_degree.lock().unwrap();
+                    for dependent in dependents {
+                        let d = in_degree.get_mut(dependent).unwrap();
+                        *d -= 1;
+                        if *d == 0 {
+                            ready.push(dependent.clone());
+                        }
+                    }
+                }
+            }
+            ready.close();
+        });
+
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Build and run a single task crate to completion, streaming its
+/// output straight through.
+///
+/// A task whose `@ct-platform@` or `@ct-condition-env@` isn't satisfied
+/// is skipped rather than run, and that's still reported as success so
+/// it doesn't block anything depending on it.
+fn run_one(
+    task: &Task,
+    cargo_task_dir: &std::path::Path,
+    env: &HashMap<String, String>,
+) -> io::Result<()> {
+    if let Some(reason) = skip_reason(&task.at_at, env) {
+        println!("skipping {}: {reason}", task.name);
+        return Ok(());
+    }
+
+    let TaskKind::Crate { .. } = &task.kind else {
+        return Err(io::Error::other(format!(
+            "single-file task '{}' at {} cannot be run yet: no on-the-fly crate generation",
+            task.name,
+            task.main_rs_path().display()
+        )));
+    };
+
+    for spec in &task.at_at.install_crate {
+        ensure_crate_installed(spec, task.at_at.install_crate_force)?;
+    }
+
+    let crate_root = task
+        .source_root()
+        .ok_or_else(|| io::Error::other(format!("malformed task crate for '{}'", task.name)))?;
+    std::fs::write(
+        task.main_rs_path().with_file_name("cargo_task_util.rs"),
+        CARGO_TASK_UTIL_SRC,
+    )?;
+
+    let manifest_path = crate_root.join("Cargo.toml");
+    let manifest_dir = std::env::current_dir()?;
+    // Each task gets its own subdirectory of the managed target dir, so
+    // `ct-clean` can tell a stale task's build output from a live one -
+    // recorded so `ct-clean` knows this subdirectory is one of its own,
+    // never something else that happens to share the target base.
+    let target_dir = resolve_target_dir(cargo_task_dir).join(&task.name);
+    env_loader::record_managed_dir(cargo_task_dir, &task.name)?;
+    let cargo_bin = env_loader::cargo_bin();
+
+    let mut cmd = Command::new(&cargo_bin);
+    cmd.arg("run")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env(ENV_MANIFEST_DIR, &manifest_dir)
+        .env(ENV_CARGO_TASK_DIR, cargo_task_dir)
+        .env(ENV_TARGET_DIR, &target_dir)
+        .env(ENV_TASK_NAME, &task.name)
+        .env(ENV_CARGO_BIN, &cargo_bin)
+        .env(ENV_EXPORT_FILE, env_loader::env_export_file(cargo_task_dir));
+
+    if let Some(cargo_home) = std::env::var_os("CARGO_HOME") {
+        cmd.env(ENV_CARGO_HOME, &cargo_home);
+    }
+
+    if let Some(snapshot) = workspace_snapshot_path(cargo_task_dir, &manifest_dir) {
+        cmd.env(ENV_WORKSPACE_FILE, snapshot);
+    }
+
+    let status = cmd.status()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "task '{}' failed with {status}",
+            task.name
+        )));
+    }
+
+    Ok(())
+}
+
+/// `cargo task --watch <path...>` - run the requested tasks, then keep
+/// re-running them every time a watched path changes, until the process
+/// is killed (Ctrl-C).
+///
+/// There's no installed signal handler: a terminal's Ctrl-C delivers
+/// `SIGINT` to the whole foreground process group at once, so
+/// `cargo-task` is killed right along with whatever task it's currently
+/// running, before it could observe that and "stop" on its own. This
+/// loop only ever exits on an error loading task metadata - otherwise it
+/// runs until something outside it ends the process.
+///
+/// By default, each watched task's own crate sources (everything under
+/// its crate directory) are watched, plus any paths it names with
+/// `@ct-watch@`. `watch.paths` adds further paths (files or directories,
+/// relative to the workspace root) on top of that, shared by every task
+/// in this run.
+///
+/// This polls mtimes on a fixed interval rather than using an OS notify
+/// API, to keep `cargo-task` dependency-free.
+fn run_watch(task_names: &[String], opts: &ExecOptions, watch: &WatchOptions) -> io::Result<()> {
+    loop {
+        if let Err(e) = run_once(task_names, opts) {
+            eprintln!("cargo task --watch: {e}");
+        }
+
+        let cargo_task_dir = PathBuf::from(CARGO_TASK_DIR);
+        let tasks = env_loader::load_tasks(&cargo_task_dir)?;
+        let names = default_task_names_if_empty(&tasks, task_names);
+        let targets = watch_targets(&tasks, &names, watch);
+
+        println!("cargo task --watch: watching for changes...");
+        let mut before = snapshot_mtimes(&targets);
+        loop {
+            std::thread::sleep(WATCH_POLL_INTERVAL);
+            let after = snapshot_mtimes(&targets);
+            if after != before {
+                // Let further changes land before re-running, so a burst
+                // of saves only triggers a single re-run.
+                let mut settled = after;
+                loop {
+                    std::thread::sleep(WATCH_DEBOUNCE);
+                    let next = snapshot_mtimes(&targets);
+                    if next == settled {
+                        break;
+                    }
+                    settled = next;
+                }
+                break;
+            }
+            before = after;
+        }
+    }
+}
+
+/// The set of paths `run_watch` should poll for a given list of task
+/// names: each task's own crate directory, its `@ct-watch@` paths, and
+/// anything passed via `--watch`.
+fn watch_targets(tasks: &[Task], names: &[String], watch: &WatchOptions) -> Vec<PathBuf> {
+    let by_name: HashMap<&str, &Task> = tasks.iter().map(|t| (t.name.as_str(), t)).collect();
+    let mut targets = Vec::new();
+
+    for name in names {
+        let Some(task) = by_name.get(name.as_str()) else {
+            continue;
+        };
+        if let Some(source_root) = task.source_root() {
+            targets.push(source_root.to_path_buf());
+        }
+        targets.extend(task.at_at.watch_paths.iter().map(PathBuf::from));
+    }
+
+    targets.extend(watch.paths.iter().map(PathBuf::from));
+    targets
+}
+
+/// Recursively collect the last-modified time of every file under each
+/// of `paths`, keyed by path, so two snapshots can be compared for
+/// equality to detect a change.
+fn snapshot_mtimes(paths: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut out = HashMap::new();
+    for path in paths {
+        collect_mtimes(path, &mut out);
+    }
+    out
+}
+
+/// Walk `path` (a file or directory), inserting the modified time of
+/// every file found into `out`. Unreadable paths are silently skipped -
+/// a path a task names but hasn't created yet just isn't watched until
+/// it exists.
+fn collect_mtimes(path: &std::path::Path, out: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return;
+    };
+
+    if metadata.is_dir() {
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            collect_mtimes(&entry.path(), out);
+        }
+        return;
+    }
+
+    if let Ok(modified) = metadata.modified() {
+        out.insert(path.to_path_buf(), modified);
+    }
+}
+
+/// Lazily run `cargo metadata` once per `cargo task` invocation and cache
+/// where its parsed snapshot was written, so every task can be handed
+/// the same file without re-shelling out to cargo for each one.
+///
+/// Returns `None` if `cargo metadata` fails - e.g. because `cargo task`
+/// isn't being run inside a cargo workspace - in which case
+/// `cargo_task_util::CTEnv::workspace` simply returns `None` to the
+/// task.
+fn workspace_snapshot_path(cargo_task_dir: &std::path::Path, manifest_dir: &std::path::Path) -> Option<PathBuf> {
+    static SNAPSHOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    SNAPSHOT
+        .get_or_init(|| {
+            let workspace = env_loader::load_workspace_metadata(manifest_dir).ok()?;
+            let path = env_loader::workspace_snapshot_file(cargo_task_dir);
+            env_loader::write_workspace_snapshot(&workspace, &path).ok()?;
+            Some(path)
+        })
+        .clone()
+}
+
+/// Binary names already confirmed present during this `cargo task`
+/// invocation, so `@ct-install-crate@` doesn't re-probe `PATH` for every
+/// task that shares a prerequisite.
+fn installed_cache() -> &'static Mutex<HashSet<String>> {
+    static CACHE: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Split an `@ct-install-crate@` entry of the form `name[@version][:bin]`
+/// into its crate name, optional version, and the binary name to probe
+/// for on `PATH` / `$CARGO_HOME/bin`.
+///
+/// The crate name and its binary name only coincide by convention (true
+/// for `cargo-nextest`, false for e.g. `ripgrep`, whose binary is `rg`) -
+/// the `:bin` suffix lets a directive say so explicitly; it defaults to
+/// the crate name when omitted.
+fn parse_install_crate_spec(spec: &str) -> (&str, Option<&str>, &str) {
+    let (name_version, bin) = match spec.split_once(':') {
+        Some((name_version, bin)) => (name_version, Some(bin)),
+        None => (spec, None),
+    };
+    let (name, version) = match name_version.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (name_version, None),
+    };
+    (name, version, bin.unwrap_or(name))
+}
+
+/// Make sure the binary crate named by `spec` (`name`, `name@version`,
+/// `name:bin`, or `name@version:bin`) is installed, running
+/// `cargo install` if its binary isn't already on `PATH` or in
+/// `$CARGO_HOME/bin`.
+fn ensure_crate_installed(spec: &str, force: bool) -> io::Result<()> {
+    let (name, version, bin_name) = parse_install_crate_spec(spec);
+
+    if !force {
+        let mut cache = installed_cache().lock().unwrap();
+        if cache.contains(bin_name) {
+            return Ok(());
+        }
+        if binary_installed(bin_name) {
+            cache.insert(bin_name.to_string());
+            return Ok(());
+        }
+    }
+
+    let mut cmd = Command::new(env_loader::cargo_bin());
+    cmd.arg("install").arg(name);
+    if let Some(version) = version {
+        cmd.arg("--version").arg(version);
+    }
+    if force {
+        cmd.arg("--force");
+    }
+
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "failed to install prerequisite crate '{name}': {status}"
+        )));
+    }
+
+    installed_cache().lock().unwrap().insert(bin_name.to_string());
+    Ok(())
+}
+
+/// Whether a binary named `name` (`name.exe` on Windows) can already be
+/// found on `PATH` or in `$CARGO_HOME/bin` (falling back to
+/// `~/.cargo/bin`).
+fn binary_installed(name: &str) -> bool {
+    let exe_name = if cfg!(windows) {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    };
+
+    let on_path = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(&exe_name).is_file()))
+        .unwrap_or(false);
+    if on_path {
+        return true;
+    }
+
+    let cargo_bin = match std::env::var_os("CARGO_HOME") {
+        Some(cargo_home) => PathBuf::from(cargo_home).join("bin"),
+        None => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".cargo").join("bin"),
+            None => return false,
+        },
+    };
+
+    cargo_bin.join(&exe_name).is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a synthetic `Task` with the given `@ct-task-deps@`, as if
+    /// discovered from a task crate's `main.rs`.
+    fn task_with_deps(name: &str, deps: &[&str]) -> Task {
+        let src = format!("/*\n@ct-task-deps@\n{}\n@@\n*/\n", deps.join("\n"));
+        Task::new(
+            name.to_string(),
+            TaskKind::Crate {
+                main_rs: PathBuf::from(format!(".cargo-task/{name}/src/main.rs")),
+            },
+            &src,
+        )
+    }
+
+    #[test]
+    fn build_orders_transitive_dependencies() {
+        let tasks = vec![
+            task_with_deps("a", &["b"]),
+            task_with_deps("b", &["c"]),
+            task_with_deps("c", &[]),
+        ];
+        let graph =
+            TaskGraph::build(&tasks, &["a".to_string()], std::path::Path::new(".cargo-task"))
+                .unwrap();
+
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(*graph.in_degree.get("c").unwrap(), 0);
+        assert_eq!(*graph.in_degree.get("b").unwrap(), 1);
+        assert_eq!(*graph.in_degree.get("a").unwrap(), 1);
+    }
+
+    #[test]
+    fn build_rejects_unknown_dependency() {
+        let tasks = vec![task_with_deps("a", &["missing"])];
+        let err = TaskGraph::build(&tasks, &["a".to_string()], std::path::Path::new(".cargo-task"))
+            .unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn build_detects_a_cycle() {
+        let tasks = vec![task_with_deps("a", &["b"]), task_with_deps("b", &["a"])];
+        let err = TaskGraph::build(&tasks, &["a".to_string()], std::path::Path::new(".cargo-task"))
+            .unwrap_err();
+        assert!(err.to_string().contains("cycle"));
+    }
+
+    #[test]
+    fn build_accepts_a_diamond() {
+        // a depends on b and c, both of which depend on d - not a cycle,
+        // just d being reachable by two paths.
+        let tasks = vec![
+            task_with_deps("a", &["b", "c"]),
+            task_with_deps("b", &["d"]),
+            task_with_deps("c", &["d"]),
+            task_with_deps("d", &[]),
+        ];
+        let graph =
+            TaskGraph::build(&tasks, &["a".to_string()], std::path::Path::new(".cargo-task"))
+                .unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+    }
+
+    #[test]
+    fn ready_queue_pop_returns_in_fifo_order() {
+        let queue = ReadyQueue::new();
+        queue.push("first".to_string());
+        queue.push("second".to_string());
+
+        assert_eq!(queue.pop().as_deref(), Some("first"));
+        assert_eq!(queue.pop().as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn ready_queue_pop_returns_none_once_closed_and_empty() {
+        let queue = ReadyQueue::new();
+        queue.close();
+        assert_eq!(queue.pop(), None);
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn skip_reason_none_when_unconstrained() {
+        let at_at = AtAt::default();
+        assert_eq!(skip_reason(&at_at, &env(&[])), None);
+    }
+
+    #[test]
+    fn skip_reason_platform_mismatch() {
+        let at_at = AtAt {
+            platform: vec!["some-os-nobody-runs".to_string()],
+            ..Default::default()
+        };
+        assert!(skip_reason(&at_at, &env(&[])).is_some());
+    }
+
+    #[test]
+    fn skip_reason_platform_match() {
+        let at_at = AtAt {
+            platform: vec![std::env::consts::OS.to_string()],
+            ..Default::default()
+        };
+        assert_eq!(skip_reason(&at_at, &env(&[])), None);
+    }
+
+    #[test]
+    fn skip_reason_bare_condition_env_present() {
+        let at_at = AtAt {
+            condition_env: vec!["CI".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(skip_reason(&at_at, &env(&[("CI", "true")])), None);
+        assert!(skip_reason(&at_at, &env(&[])).is_some());
+    }
+
+    #[test]
+    fn skip_reason_keyed_condition_env_must_match_value() {
+        let at_at = AtAt {
+            condition_env: vec!["CI=true".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(skip_reason(&at_at, &env(&[("CI", "true")])), None);
+        assert!(skip_reason(&at_at, &env(&[("CI", "false")])).is_some());
+        assert!(skip_reason(&at_at, &env(&[])).is_some());
+    }
+
+    #[test]
+    fn install_crate_spec_bare_name() {
+        assert_eq!(
+            parse_install_crate_spec("cargo-nextest"),
+            ("cargo-nextest", None, "cargo-nextest")
+        );
+    }
+
+    #[test]
+    fn install_crate_spec_with_version() {
+        assert_eq!(
+            parse_install_crate_spec("cargo-nextest@0.9"),
+            ("cargo-nextest", Some("0.9"), "cargo-nextest")
+        );
+    }
+
+    #[test]
+    fn install_crate_spec_with_explicit_binary() {
+        assert_eq!(
+            parse_install_crate_spec("ripgrep:rg"),
+            ("ripgrep", None, "rg")
+        );
+    }
+
+    #[test]
+    fn install_crate_spec_with_version_and_explicit_binary() {
+        assert_eq!(
+            parse_install_crate_spec("ripgrep@13.0.0:rg"),
+            ("ripgrep", Some("13.0.0"), "rg")
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! A tiny, read-only JSON parser.
+//!
+//! `cargo-task` is zero-dependency, so rather than pull in `serde_json`
+//! just to read a handful of fields out of `cargo metadata` output, this
+//! parses JSON text into a generic [`Value`] tree that callers can pick
+//! the fields they need back out of.
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// Any JSON number, stored as `f64`.
+    Number(f64),
+    /// A JSON string, with escapes already resolved.
+    String(String),
+    /// A JSON array.
+    Array(Vec<Value>),
+    /// A JSON object, keeping its original key order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// If this is an object, look up `key` in it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// This value as a `&str`, if it's a JSON string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// This value as a slice, if it's a JSON array.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a complete JSON document from `input`.
+pub fn parse(input: &str) -> Result<Value, String> {
+    let mut p = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+    };
+    p.skip_whitespace();
+    let value = p.parse_value()?;
+    p.skip_whitespace();
+    if p.pos != p.bytes.len() {
+        return Err(format!("trailing characters at byte {}", p.pos));
+    }
+    Ok(value)
+}
+
+/// A cursor over the input bytes. JSON is ASCII-structural even when it
+/// carries UTF-8 string content, so byte indexing is safe here as long as
+/// string contents are sliced out via `str::from_utf8`.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        match self.bump() {
+            Some(b) if b == byte => Ok(()),
+            other => Err(format!(
+                "expected '{}' at byte {}, found {:?}",
+                byte as char,
+                self.pos,
+                other.map(|b| b as char)
+            )),
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &str, value: Value) -> Result<Value, String> {
+        for want in literal.bytes() {
+            self.expect(want)?;
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"') => self.parse_string().map(Value::String),
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b't') => self.expect_literal("true", Value::Bool(true)),
+            Some(b'f') => self.expect_literal("false", Value::Bool(false)),
+            Some(b'n') => self.expect_literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            other => Err(format!("unexpected byte {other:?} at {}", self.pos)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            match self.bump().ok_or("unterminated string")? {
+                b'"' => return Ok(out),
+                b'\\' => match self.bump().ok_or("unterminated escape")? {
+                    b'"' => out.push('"'),
+                    b'\\' => out.push('\\'),
+                    b'/' => out.push('/'),
+                    b'n' => out.push('\n'),
+                    b't' => out.push('\t'),
+                    b'r' => out.push('\r'),
+                    b'b' => out.push('\u{8}'),
+                    b'f' => out.push('\u{c}'),
+                    b'u' => {
+                        let code = self.parse_hex4()?;
+                        out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                    }
+                    other => return Err(format!("unknown escape '\\{}'", other as char)),
+                },
+                b => {
+                    // Reassemble UTF-8 continuation bytes verbatim.
+                    let start = self.pos - 1;
+                    let mut end = self.pos;
+                    if b >= 0x80 {
+                        while end < self.bytes.len() && self.bytes[end] & 0xC0 == 0x80 {
+                            end += 1;
+                        }
+                        self.pos = end;
+                    }
+                    out.push_str(std::str::from_utf8(&self.bytes[start..end]).map_err(|e| e.to_string())?);
+                }
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0u32;
+        for _ in 0..4 {
+            let b = self.bump().ok_or("unterminated \\u escape")?;
+            let digit = (b as char).to_digit(16).ok_or("invalid \\u escape")?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|e| e.to_string())?;
+        text.parse::<f64>()
+            .map(Value::Number)
+            .map_err(|e| e.to_string())
+    }
+
+    fn parse_array(&mut self) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.bump() {
+                Some(b',') => continue,
+                Some(b']') => break,
+                other => return Err(format!("expected ',' or ']', found {other:?}")),
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn parse_object(&mut self) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+            fields.push((key, value));
+            self.skip_whitespace();
+            match self.bump() {
+                Some(b',') => continue,
+                Some(b'}') => break,
+                other => return Err(format!("expected ',' or '}}', found {other:?}")),
+            }
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scalars() {
+        assert_eq!(parse("null").unwrap(), Value::Null);
+        assert_eq!(parse("true").unwrap(), Value::Bool(true));
+        assert_eq!(parse("false").unwrap(), Value::Bool(false));
+        assert_eq!(parse("-12.5e2").unwrap(), Value::Number(-1250.0));
+    }
+
+    #[test]
+    fn parses_string_escapes() {
+        let value = parse(r#""a\n\t\"bé""#).unwrap();
+        assert_eq!(value, Value::String("a\n\t\"b\u{e9}".to_string()));
+    }
+
+    #[test]
+    fn parses_string_with_utf8_content() {
+        let value = parse(r#""héllo🦀""#).unwrap();
+        assert_eq!(value, Value::String("héllo🦀".to_string()));
+    }
+
+    #[test]
+    fn parses_empty_array_and_object() {
+        assert_eq!(parse("[]").unwrap(), Value::Array(vec![]));
+        assert_eq!(parse("{}").unwrap(), Value::Object(vec![]));
+    }
+
+    #[test]
+    fn parses_nested_structures_and_whitespace() {
+        let value = parse(
+            r#"
+            {
+                "name": "cargo-task",
+                "tags": [ "a", 1, true, null ],
+                "nested": { "inner": [] }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value.get("name").and_then(Value::as_str),
+            Some("cargo-task")
+        );
+        let tags = value.get("tags").and_then(Value::as_array).unwrap();
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags[1], Value::Number(1.0));
+        assert_eq!(
+            value.get("nested").and_then(|v| v.get("inner")),
+            Some(&Value::Array(vec![]))
+        );
+    }
+
+    #[test]
+    fn object_preserves_key_order() {
+        let value = parse(r#"{"b": 1, "a": 2}"#).unwrap();
+        let Value::Object(fields) = value else {
+            panic!("expected object");
+        };
+        assert_eq!(fields[0].0, "b");
+        assert_eq!(fields[1].0, "a");
+    }
+
+    #[test]
+    fn get_and_as_helpers_return_none_on_type_mismatch() {
+        let value = parse("42").unwrap();
+        assert_eq!(value.get("anything"), None);
+        assert_eq!(value.as_str(), None);
+        assert_eq!(value.as_array(), None);
+    }
+
+    #[test]
+    fn rejects_unterminated_string() {
+        assert!(parse(r#""unterminated"#).is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_comma() {
+        assert!(parse("[1,]").is_err());
+        assert!(parse(r#"{"a": 1,}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_literal() {
+        assert!(parse("nul").is_err());
+        assert!(parse("tru").is_err());
+    }
+}
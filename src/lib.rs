@@ -126,6 +126,114 @@
 //! A whitespace delimited list of tasks that must be run prior to the current
 //! task. Can be on a single line or multiple lines.
 //!
+//! ### Platform- and condition-gated tasks.
+//!
+//! ```ignore
+//! /*
+//! @ct-platform@ linux macos @@
+//! @ct-condition-env@ CI=true @@
+//! */
+//! ```
+//!
+//! `@ct-platform@` restricts a task to the listed `std::env::consts::OS`
+//! values, and `@ct-condition-env@` restricts it to an environment where
+//! the named variable is set (`NAME`) or set to a specific value
+//! (`NAME=value`). A task whose platform or condition isn't met is
+//! skipped rather than failed, and a skipped task still counts as
+//! satisfied for anything that depends on it.
+//!
+//! ### Auto-installing binary crate prerequisites.
+//!
+//! ```ignore
+//! /*
+//! @ct-install-crate@ cargo-nextest@0.9 @@
+//! */
+//! ```
+//!
+//! Lists binary crates (like `cargo-nextest`) this task shells out to.
+//! Before the task runs, `cargo-task` checks whether each one is already
+//! on `PATH` or in `$CARGO_HOME/bin`, and runs `cargo install` for any
+//! that are missing. Add a bare `--force` entry to always reinstall.
+//! Crates already confirmed present are cached for the rest of the
+//! `cargo task` invocation, so sharing a prerequisite across many tasks
+//! only probes for it once.
+//!
+//! The installed-binary probe assumes the crate's binary shares its
+//! name, which isn't always true (`ripgrep` installs `rg`, not
+//! `ripgrep`). For those, name the binary explicitly with a `:bin`
+//! suffix:
+//!
+//! ```ignore
+//! /*
+//! @ct-install-crate@ ripgrep:rg @@
+//! */
+//! ```
+//!
+//! ### Running tasks in parallel.
+//!
+//! ```shell
+//! cargo task --jobs 4 one two three
+//! ```
+//!
+//! By default, `cargo task` runs the requested tasks (and their
+//! `@ct-task-deps@`) one at a time. Pass `--jobs N` to build a dependency
+//! graph of everything that needs to run and dispatch up to `N` of the
+//! tasks whose dependencies have already completed at once. Bootstrap
+//! tasks are unaffected - they always run first, sequentially, as a
+//! barrier.
+//!
+//! ### Watch mode.
+//!
+//! ```shell
+//! cargo task --watch my-task
+//! cargo task --watch assets/ docs/ my-task
+//! ```
+//!
+//! Runs the requested tasks, then keeps re-running them every time a
+//! watched path changes, until you hit Ctrl-C. By default each watched
+//! task's own crate sources are watched; add more paths (files or
+//! directories, relative to the workspace root) either on the command
+//! line or via a task's own `@ct-watch@` directive:
+//!
+//! ```ignore
+//! /*
+//! @ct-watch@
+//! assets
+//! @@
+//! */
+//! ```
+//!
+//! Watching works by polling mtimes on a short interval and debouncing a
+//! burst of changes into a single re-run - no OS notify API, so it stays
+//! as dependency-free as the rest of `cargo-task`.
+//!
+//! ### Cleaning up build artifacts.
+//!
+//! ```shell
+//! cargo task ct-clean
+//! cargo task ct-clean --all
+//! ```
+//!
+//! `cargo task` gives each task its own subdirectory of the managed
+//! target directory (see "Playing nicely with custom toolchains" below),
+//! so `ct-clean` can tell a stale task's build output from a live one.
+//! Plain `cargo task ct-clean` removes only the subdirectories of tasks
+//! that no longer exist (renamed or deleted); `--all` removes every
+//! task's subdirectory, live or not. Either way, `ct-clean` only ever
+//! touches subdirectories it created itself - never the target directory
+//! as a whole, which matters when `CARGO_TARGET_DIR` points at a
+//! directory shared with the workspace's own `debug`/`release`/`doc`
+//! output. Any paths a task lists via `@ct-clean@` are reclaimed too:
+//!
+//! ```ignore
+//! /*
+//! @ct-clean@
+//! dist
+//! coverage/report.html
+//! @@
+//! */
+//! ```
+//!
 //! ## The magic `cargo_task_util` module.
 //!
 //! - [cargo_task_util on docs.rs](https://docs.rs/cargo-task/latest/cargo_task/cargo_task_util/index.html)
@@ -154,6 +262,47 @@
 //! }
 //! ```
 //!
+//! ## Reading workspace metadata from a task.
+//!
+//! `cargo_task_util::CTEnv::workspace` hands you the invoking crate's
+//! `cargo metadata`, already parsed: member package names, versions,
+//! manifest paths, and the shared target directory. It's only run once
+//! per `cargo task` invocation, no matter how many tasks (or how many
+//! times `env.workspace()`) ask for it.
+//!
+//! ```ignore
+//! /*
+//! @ct-default@ true @@
+//! */
+//!
+//! mod cargo_task_util;
+//! use cargo_task_util::*;
+//!
+//! fn main() {
+//!     let env = ct_env();
+//!     if let Some(workspace) = env.workspace() {
+//!         for member in workspace.members {
+//!             println!("{} {}", member.name, member.version);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Playing nicely with custom toolchains.
+//!
+//! `cargo-task` builds and runs your `.cargo-task` crates by shelling out
+//! to `cargo` itself. When it's run as a cargo subcommand, Cargo sets the
+//! `CARGO` environment variable to the exact binary that invoked it, and
+//! `cargo-task` uses that (falling back to plain `cargo` on `PATH` only
+//! if it's unset), so you stay on the same toolchain - rustup override,
+//! custom build of cargo, or otherwise.
+//!
+//! `CARGO_HOME` and `CARGO_TARGET_DIR` are honored the same way: if
+//! you've set them, task-crate build artifacts land there instead of a
+//! default guess. `cargo_task_util::CTEnv` exposes all three
+//! (`cargo_bin`, `cargo_home`, `target_dir`) so a task can launch further
+//! cargo commands with the exact same settings.
+//!
 //! ## Exporting environment variables to configure other tasks.
 //!
 //! `cargo_task_util::CTEnv` also includes a utility for exporting environment
@@ -187,6 +336,7 @@
 pub mod at_at;
 pub mod cargo_task_util;
 mod env_loader;
+mod json;
 mod task;
 
 #[cfg(windows)]
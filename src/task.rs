@@ -0,0 +1,104 @@
+//! Discovery of individual tasks within the `.cargo-task` directory.
+
+use crate::at_at::AtAt;
+use std::path::{Path, PathBuf};
+
+/// The two shapes a task's source can take on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskKind {
+    /// A full crate directory containing its own `Cargo.toml` and
+    /// `src/main.rs`.
+    Crate {
+        /// Path to the task crate's `main.rs`.
+        main_rs: PathBuf,
+    },
+    /// A single `some-name.ct.rs` file. Such a task is discovered and its
+    /// AtAt directives are parsed, but no crate is generated for it, so
+    /// running it fails with an error.
+    SingleFile {
+        /// Path to the `.ct.rs` file itself.
+        path: PathBuf,
+    },
+}
+
+/// A single discovered task: its name, where its source lives, and the
+/// AtAt metadata parsed out of that source.
+#[derive(Debug, Clone)]
+pub struct Task {
+    /// The task name, as used on the `cargo task <name>` command line.
+    pub name: String,
+
+    /// Where the task's source lives on disk.
+    pub kind: TaskKind,
+
+    /// Parsed `@ct-*@` directives from the task's `main.rs`.
+    pub at_at: AtAt,
+}
+
+impl Task {
+    /// Build a [`Task`] from its name, on-disk location, and already-read
+    /// source text (so callers that already had to read the file to
+    /// discover it don't need to read it twice).
+    pub fn new(name: String, kind: TaskKind, main_rs_src: &str) -> Self {
+        Self {
+            name,
+            kind,
+            at_at: AtAt::parse(main_rs_src),
+        }
+    }
+
+    /// The path to the file `cargo-task` should parse for AtAt directives
+    /// and that ultimately gets compiled as `main.rs`.
+    pub fn main_rs_path(&self) -> &Path {
+        match &self.kind {
+            TaskKind::Crate { main_rs } => main_rs,
+            TaskKind::SingleFile { path } => path,
+        }
+    }
+
+    /// The root directory to watch for source changes: the crate
+    /// directory for a [`TaskKind::Crate`] task (`main_rs`'s grandparent,
+    /// since `main_rs` is `<crate>/src/main.rs`), or the directory
+    /// directly containing the file for a [`TaskKind::SingleFile`] task.
+    ///
+    /// `None` if a `Crate` task's `main.rs` isn't nested two directories
+    /// deep as expected - a malformed task.
+    pub fn source_root(&self) -> Option<&Path> {
+        match &self.kind {
+            TaskKind::Crate { main_rs } => main_rs.parent()?.parent(),
+            TaskKind::SingleFile { path } => path.parent(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crate_task_source_root_is_the_crate_directory() {
+        let task = Task::new(
+            "my-task".to_string(),
+            TaskKind::Crate {
+                main_rs: PathBuf::from(".cargo-task/my-task/src/main.rs"),
+            },
+            "",
+        );
+        assert_eq!(
+            task.source_root(),
+            Some(Path::new(".cargo-task/my-task"))
+        );
+    }
+
+    #[test]
+    fn single_file_task_source_root_is_its_own_directory() {
+        let task = Task::new(
+            "my-task".to_string(),
+            TaskKind::SingleFile {
+                path: PathBuf::from(".cargo-task/my-task.ct.rs"),
+            },
+            "",
+        );
+        assert_eq!(task.source_root(), Some(Path::new(".cargo-task")));
+    }
+}